@@ -0,0 +1,61 @@
+/// Color space the swap heuristic measures distance in. `Lab` tracks human
+/// perception far better than raw sRGB, which over-weights green and
+/// under-weights blue when used as a flat Euclidean distance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    #[default]
+    Rgb,
+    Lab,
+}
+
+/// D65 reference white (the white point the sRGB->XYZ matrix in
+/// `srgb_to_lab` is defined for), used for the XYZ->Lab normalization.
+const WHITE: [f32; 3] = [0.95047, 1.0, 1.08883];
+
+fn srgb_channel_to_linear(c: f32) -> f32 {
+    let c = c / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn lab_f(t: f32) -> f32 {
+    const DELTA: f32 = 6.0 / 29.0;
+    if t > DELTA.powi(3) {
+        t.cbrt()
+    } else {
+        t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+    }
+}
+
+/// sRGB (0-255 per channel) -> CIELAB, via linear-light sRGB and D65 XYZ.
+pub fn srgb_to_lab(rgb: (u8, u8, u8)) -> [f32; 3] {
+    let r = srgb_channel_to_linear(rgb.0 as f32);
+    let g = srgb_channel_to_linear(rgb.1 as f32);
+    let b = srgb_channel_to_linear(rgb.2 as f32);
+
+    // sRGB D65 linear -> XYZ.
+    let x = 0.4124564 * r + 0.3575761 * g + 0.1804375 * b;
+    let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+    let z = 0.0193339 * r + 0.1191920 * g + 0.9503041 * b;
+
+    let fx = lab_f(x / WHITE[0]);
+    let fy = lab_f(y / WHITE[1]);
+    let fz = lab_f(z / WHITE[2]);
+
+    let l = 116.0 * fy - 16.0;
+    let a = 500.0 * (fx - fy);
+    let b = 200.0 * (fy - fz);
+    [l, a, b]
+}
+
+/// Converts a raw sRGB triplet into the coordinates the heuristic should
+/// actually compare, according to `space`.
+pub fn to_space(rgb: (u8, u8, u8), space: ColorSpace) -> [f32; 3] {
+    match space {
+        ColorSpace::Rgb => [rgb.0 as f32, rgb.1 as f32, rgb.2 as f32],
+        ColorSpace::Lab => srgb_to_lab(rgb),
+    }
+}
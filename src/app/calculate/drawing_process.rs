@@ -10,27 +10,145 @@ use std::sync::{Arc, atomic::AtomicU32, mpsc};
 #[cfg(not(target_arch = "wasm32"))]
 use super::ProgressMsg;
 
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+
 use super::GenerationSettings;
+use super::SWAPS_PER_GENERATION_PER_PIXEL;
 
 #[derive(Clone, Copy)]
 pub struct PixelData {
     pub stroke_id: u32,
     pub last_edited: u32,
 }
+
+/// Number of bands the turbulence field is quantized into when seeding
+/// stroke ids — enough to produce visibly distinct flowing regions without
+/// fragmenting into single-pixel strokes.
+const TURBULENCE_STROKE_BANDS: u32 = 24;
+
+/// Fixed so the same `StrokeSeed::Turbulence` parameters always produce the
+/// same initial stroke layout.
+const TURBULENCE_PERLIN_SEED: u64 = 0x5EED_1234_ABCD_EF01;
+
 impl PixelData {
-    pub(crate) fn init_canvas(frame_count: u32) -> Vec<PixelData> {
-        vec![
-            PixelData {
-                stroke_id: 0,
-                last_edited: frame_count
+    pub(crate) fn init_canvas(frame_count: u32, seed: calculate::StrokeSeed) -> Vec<PixelData> {
+        match seed {
+            calculate::StrokeSeed::Grid => vec![
+                PixelData {
+                    stroke_id: 0,
+                    last_edited: frame_count
+                };
+                DRAWING_CANVAS_SIZE * DRAWING_CANVAS_SIZE
+            ],
+            calculate::StrokeSeed::Turbulence {
+                octaves,
+                frequency,
+                persistence,
+            } => {
+                let perlin = calculate::noise::Perlin::new(TURBULENCE_PERLIN_SEED);
+                (0..DRAWING_CANVAS_SIZE * DRAWING_CANVAS_SIZE)
+                    .map(|i| {
+                        let x = (i % DRAWING_CANVAS_SIZE) as f32;
+                        let y = (i / DRAWING_CANVAS_SIZE) as f32;
+                        let noise = calculate::noise::turbulence(
+                            &perlin, x, y, octaves, frequency, persistence,
+                        );
+                        let normalized = ((noise + 1.0) * 0.5).clamp(0.0, 1.0);
+                        let stroke_id = ((normalized * TURBULENCE_STROKE_BANDS as f32) as u32)
+                            .min(TURBULENCE_STROKE_BANDS - 1);
+                        PixelData {
+                            stroke_id,
+                            last_edited: frame_count,
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Derives the initial source-pixel permutation for a `sidelen`-wide canvas
+/// from the same turbulence field `init_canvas` uses to seed `stroke_id`, so
+/// `StrokeSeed::Turbulence` also nudges the starting placement along the
+/// local flow instead of leaving it as a plain identity grid. Returns `None`
+/// for `StrokeSeed::Grid`, where identity placement is kept as-is.
+///
+/// Each step swaps a cell with one of its 4-connected neighbors chosen by
+/// the local noise value, which keeps the result a valid permutation (every
+/// source pixel is still placed exactly once) rather than an arbitrary
+/// per-pixel remap.
+fn turbulence_initial_placement(
+    sidelen: usize,
+    seed: calculate::StrokeSeed,
+) -> Option<Vec<(u16, u16)>> {
+    let calculate::StrokeSeed::Turbulence {
+        octaves,
+        frequency,
+        persistence,
+    } = seed
+    else {
+        return None;
+    };
+
+    let perlin = calculate::noise::Perlin::new(TURBULENCE_PERLIN_SEED);
+    let mut placement: Vec<(u16, u16)> = (0..sidelen * sidelen)
+        .map(|i| ((i % sidelen) as u16, (i / sidelen) as u16))
+        .collect();
+
+    // Serpentine raster pass, flipping direction each row like the
+    // Floyd-Steinberg dither pass, so no directional bias accumulates.
+    for y in 0..sidelen {
+        let xs: Box<dyn Iterator<Item = usize>> = if y % 2 == 0 {
+            Box::new(0..sidelen)
+        } else {
+            Box::new((0..sidelen).rev())
+        };
+        for x in xs {
+            let noise = calculate::noise::turbulence(
+                &perlin, x as f32, y as f32, octaves, frequency, persistence,
+            );
+            // Quantize the noise value into one of the 4-connected
+            // directions and swap with that neighbor, biasing placement
+            // along the local turbulence flow.
+            let (dx, dy): (i32, i32) = match (((noise + 1.0) * 2.0) as u32).min(3) {
+                0 => (1, 0),
+                1 => (0, 1),
+                2 => (-1, 0),
+                _ => (0, -1),
             };
-            DRAWING_CANVAS_SIZE * DRAWING_CANVAS_SIZE
-        ]
+            let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+            if nx < 0 || ny < 0 || nx as usize >= sidelen || ny as usize >= sidelen {
+                continue;
+            }
+            placement.swap(y * sidelen + x, ny as usize * sidelen + nx as usize);
+        }
     }
+
+    Some(placement)
 }
 
 pub const DRAWING_CANVAS_SIZE: usize = 128;
 
+/// Edge length of the tiling grid used by the parallel swap search.
+/// `DRAWING_CANVAS_SIZE` must be a multiple of this, and it must be even
+/// (see `HALO_SIZE`).
+pub const TILE_SIZE: usize = 16;
+pub const TILES_PER_SIDE: usize = DRAWING_CANVAS_SIZE / TILE_SIZE;
+
+/// How far a tile's swap partner search is allowed to reach past its own
+/// boundary. Kept at half a tile so that two same-phase tiles — which, under
+/// the 4-phase schedule in `run_tiled_pass`, are never closer than 2 tiles
+/// apart along some axis — never have overlapping write regions: tile A's
+/// region extends to `x0 + TILE_SIZE + HALO_SIZE`, the next same-phase
+/// tile's starts no earlier than `x0 + 2*TILE_SIZE - HALO_SIZE`, and those
+/// are equal when `HALO_SIZE == TILE_SIZE / 2`. Because those write regions
+/// are only equal, not separated, `run_tile` additionally keeps the swap
+/// partner one cell shy of this boundary on each side — `stroke_reward_neighbors`
+/// reads one cell past the partner, which would otherwise land exactly on
+/// the neighboring tile's first writable index.
+const HALO_SIZE: usize = TILE_SIZE / 2;
+
 use super::heuristic;
 
 #[derive(Clone, Copy)]
@@ -69,23 +187,15 @@ impl DrawingPixel {
     pub(crate) fn calc_drawing_heuristic(
         &self,
         target_pos: (u16, u16),
-        target_col: (u8, u8, u8),
+        target_col: [f32; 3],
         weight: i64,
-        colors: &[SeedColor],
+        palette: &[[f32; 3]],
         proximity_importance: i64,
     ) -> i64 {
         heuristic(
             (self.src_x, self.src_y),
             target_pos,
-            {
-                let rgba =
-                    colors[self.src_y as usize * DRAWING_CANVAS_SIZE + self.src_x as usize].rgba;
-                (
-                    (rgba[0] * 256.0) as u8,
-                    (rgba[1] * 256.0) as u8,
-                    (rgba[2] * 256.0) as u8,
-                )
-            },
+            palette[self.src_y as usize * DRAWING_CANVAS_SIZE + self.src_x as usize],
             target_col,
             weight,
             proximity_importance,
@@ -93,11 +203,112 @@ impl DrawingPixel {
     }
 }
 
+/// Resolves the `SeedColor` set a run should build its palette from: the
+/// caller-supplied `colors` unchanged, unless `GenerationSettings::palette_size`
+/// asks for an automatically derived palette, in which case
+/// `palette::derive_palette` samples the source image resized down to the
+/// `sidelen`x`sidelen` working canvas (same resampling `util::get_images`
+/// does for `source_pixels`), rather than every pixel of a multi-megapixel
+/// source. Each of those canvas pixels is then given its nearest derived
+/// palette entry, so the result is still one `SeedColor` per canvas pixel.
+fn resolve_colors(
+    source_img: &image::RgbaImage,
+    settings: &GenerationSettings,
+    colors: &[SeedColor],
+) -> Vec<SeedColor> {
+    if settings.palette_size == 0 {
+        return colors.to_vec();
+    }
+    let source_small = image::imageops::resize(
+        source_img,
+        settings.sidelen,
+        settings.sidelen,
+        image::imageops::FilterType::Triangle,
+    );
+    let pixels: Vec<(u8, u8, u8)> = source_small.pixels().map(|p| (p[0], p[1], p[2])).collect();
+    let derived = calculate::palette::derive_palette(&pixels, settings.palette_size as usize);
+    pixels
+        .iter()
+        .map(|&(r, g, b)| {
+            let dist = |c: &SeedColor| {
+                let dr = c.rgba[0] * 256.0 - r as f32;
+                let dg = c.rgba[1] * 256.0 - g as f32;
+                let db = c.rgba[2] * 256.0 - b as f32;
+                dr * dr + dg * dg + db * db
+            };
+            *derived.iter().min_by(|x, y| dist(x).total_cmp(&dist(y))).unwrap()
+        })
+        .collect()
+}
+
+/// Converts a `SeedColor` grid into the coordinates the heuristic measures
+/// distance in. Called once per `colors` snapshot (construction, or once per
+/// generation in the background solver) rather than on every swap.
+pub(crate) fn palette_in_color_space(
+    colors: &[SeedColor],
+    space: calculate::ColorSpace,
+) -> Vec<[f32; 3]> {
+    colors
+        .iter()
+        .map(|c| {
+            let rgba = c.rgba;
+            let rgb = (
+                (rgba[0] * 256.0) as u8,
+                (rgba[1] * 256.0) as u8,
+                (rgba[2] * 256.0) as u8,
+            );
+            calculate::color::to_space(rgb, space)
+        })
+        .collect()
+}
+
+/// Unsynchronized shared access to the pixel buffer during a tiled pass.
+///
+/// Safety invariant: callers must only hand out `TileBuffer`s across workers
+/// that are scheduled so that no two concurrently-running tiles (including
+/// their halo) ever read or write the same index. The four-phase schedule
+/// in `run_tiled_pass`, paired with the half-tile `HALO_SIZE`, upholds this:
+/// within a phase every tile shares both `tile_x % 2` and `tile_y % 2` with
+/// every other active tile, so the nearest other active tile is always 2
+/// tiles away along the x or y axis, and a half-tile halo on each side
+/// can't bridge that 2-tile gap.
+struct TileBuffer {
+    ptr: *mut DrawingPixel,
+    len: usize,
+}
+
+unsafe impl Send for TileBuffer {}
+unsafe impl Sync for TileBuffer {}
+
+impl TileBuffer {
+    fn new(pixels: &mut [DrawingPixel]) -> Self {
+        Self {
+            ptr: pixels.as_mut_ptr(),
+            len: pixels.len(),
+        }
+    }
+
+    #[inline(always)]
+    fn get(&self, idx: usize) -> DrawingPixel {
+        debug_assert!(idx < self.len);
+        unsafe { *self.ptr.add(idx) }
+    }
+
+    #[inline(always)]
+    fn set(&self, idx: usize, value: DrawingPixel) {
+        debug_assert!(idx < self.len);
+        unsafe {
+            *self.ptr.add(idx) = value;
+        }
+    }
+}
+
 pub struct DrawingState {
     pixels: Vec<DrawingPixel>,
     rng: frand::Rand,
     settings: GenerationSettings,
-    target_pixels: Vec<(u8, u8, u8)>,
+    target_pixels: Vec<[f32; 3]>,
+    palette: Vec<[f32; 3]>,
     weights: Vec<i64>,
 }
 
@@ -114,8 +325,21 @@ impl DrawingState {
             source.source_img.clone(),
         )
         .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid source image"))?;
-        let (source_pixels, target_pixels, weights) =
-            calculate::util::get_images(source_img, &settings)?;
+        let resolved_colors = resolve_colors(&source_img, &settings, colors);
+        let colors = resolved_colors.as_slice();
+        let (source_pixels, target_pixels_raw, weights) =
+            calculate::util::get_images(source_img, &settings, colors)?;
+
+        // Precomputed once here so the hot swap loop in `step` never has to
+        // re-derive a palette color's Lab/RGB coordinates.
+        let palette = palette_in_color_space(colors, settings.color_space);
+        let target_pixels: Vec<[f32; 3]> = target_pixels_raw
+            .iter()
+            .map(|&c| calculate::color::to_space(c, settings.color_space))
+            .collect();
+
+        let initial_placement =
+            turbulence_initial_placement(settings.sidelen as usize, settings.stroke_seed);
 
         let pixels = source_pixels
             .iter()
@@ -123,12 +347,13 @@ impl DrawingState {
             .map(|(i, _)| {
                 let x = (i as u32 % settings.sidelen) as u16;
                 let y = (i as u32 / settings.sidelen) as u16;
-                let mut p = DrawingPixel::new(x, y, 0);
+                let (src_x, src_y) = initial_placement.as_ref().map_or((x, y), |p| p[i]);
+                let mut p = DrawingPixel::new(src_x, src_y, 0);
                 let h = p.calc_drawing_heuristic(
                     (x, y),
                     target_pixels[i],
                     weights[i],
-                    colors,
+                    &palette,
                     settings.proximity_importance,
                 );
                 p.update_heuristic(h);
@@ -141,79 +366,37 @@ impl DrawingState {
             rng: frand::Rand::with_seed(12345),
             settings,
             target_pixels,
+            palette,
             weights,
         })
     }
 
     pub fn step(
         &mut self,
-        colors: &[SeedColor],
         pixel_data: &[PixelData],
         frame_count: u32,
         max_swaps: usize,
         params: &DrawingParams,
+        generation: u32,
     ) -> Option<Vec<usize>> {
-        let mut swaps_made = 0;
-
-        for _ in 0..max_swaps {
-            let apos = self.rng.gen_range(0..self.pixels.len() as u64) as usize;
-            let ax = apos as u16 % self.settings.sidelen as u16;
-            let ay = apos as u16 / self.settings.sidelen as u16;
-
-            let max_dist_a = params.max_dist(frame_count.saturating_sub(pixel_data[apos].last_edited));
-
-            let bx =
-                (ax as i16 + self.rng.gen_range(-(max_dist_a as i16)..(max_dist_a as i16 + 1)))
-                    .clamp(0, self.settings.sidelen as i16 - 1) as u16;
-            let by =
-                (ay as i16 + self.rng.gen_range(-(max_dist_a as i16)..(max_dist_a as i16 + 1)))
-                    .clamp(0, self.settings.sidelen as i16 - 1) as u16;
-            let bpos = by as usize * self.settings.sidelen as usize + bx as usize;
-
-            let max_dist_b = params.max_dist(frame_count.saturating_sub(pixel_data[bpos].last_edited));
-            if (bx as i32 - ax as i32).abs() > max_dist_b as i32
-                || (by as i32 - ay as i32).abs() > max_dist_b as i32
-            {
-                continue;
-            }
-
-            let t_a = self.target_pixels[apos];
-            let t_b = self.target_pixels[bpos];
-
-            let current_a = self.pixels[apos].h
-                + stroke_reward_with_params(apos, apos, pixel_data, &self.pixels, frame_count, params);
-            let current_b = self.pixels[bpos].h
-                + stroke_reward_with_params(bpos, bpos, pixel_data, &self.pixels, frame_count, params);
-
-            let a_on_b_base = self.pixels[apos].calc_drawing_heuristic(
-                (bx, by),
-                t_b,
-                self.weights[bpos],
-                colors,
-                self.settings.proximity_importance,
-            );
-            let b_on_a_base = self.pixels[bpos].calc_drawing_heuristic(
-                (ax, ay),
-                t_a,
-                self.weights[apos],
-                colors,
-                self.settings.proximity_importance,
-            );
-            let a_on_b_h = a_on_b_base
-                + stroke_reward_with_params(bpos, apos, pixel_data, &self.pixels, frame_count, params);
-
-            let b_on_a_h = b_on_a_base
-                + stroke_reward_with_params(apos, bpos, pixel_data, &self.pixels, frame_count, params);
-
-            let improvement_a = current_a - b_on_a_h;
-            let improvement_b = current_b - a_on_b_h;
-            if improvement_a + improvement_b > 0 {
-                self.pixels.swap(apos, bpos);
-                self.pixels[apos].update_heuristic(b_on_a_base);
-                self.pixels[bpos].update_heuristic(a_on_b_base);
-                swaps_made += 1;
-            }
-        }
+        let swaps_made = run_tiled_pass(
+            &mut self.pixels,
+            pixel_data,
+            &self.target_pixels,
+            &self.weights,
+            &self.palette,
+            self.settings.sidelen as usize,
+            self.settings.proximity_importance,
+            frame_count,
+            params,
+            generation,
+            max_swaps,
+        );
+
+        // `self.rng` is not used by the tiled search (each tile seeds its
+        // own generator); it is only advanced once per call here, and the
+        // drawn value is discarded.
+        let _ = self.rng.gen_range(0..u64::MAX);
 
         if swaps_made > 0 {
             Some(
@@ -230,20 +413,233 @@ impl DrawingState {
     }
 }
 
-pub(crate) fn stroke_reward_with_params(
+/// Runs one generation over every tile of the canvas using a four-phase
+/// schedule: phase `p` mutates only tiles where `tile_x % 2 == p % 2` and
+/// `tile_y % 2 == p / 2`. Within a phase, tiles run in parallel (via rayon
+/// on native targets, sequentially on wasm32 where there is no thread
+/// pool); since every active tile in a phase shares both parities, the
+/// nearest other active tile is always 2 tiles away along the x or y axis,
+/// so a tile's half-tile halo (see `HALO_SIZE`) never overlaps a
+/// concurrently-running tile's own cells or halo.
+#[allow(clippy::too_many_arguments)]
+fn run_tiled_pass(
+    pixels: &mut [DrawingPixel],
+    pixel_data: &[PixelData],
+    target_pixels: &[[f32; 3]],
+    weights: &[i64],
+    palette: &[[f32; 3]],
+    sidelen: usize,
+    proximity_importance: i64,
+    frame_count: u32,
+    params: &DrawingParams,
+    generation: u32,
+    max_swaps: usize,
+) -> usize {
+    let tile_count = TILES_PER_SIDE * TILES_PER_SIDE;
+    // Spread `max_swaps` across tiles without ever exceeding it: each tile
+    // gets the floor, and the first `extra_tiles` tiles get one more, so the
+    // total stays exactly `max_swaps` (in particular, `max_swaps == 0` stays
+    // a genuine no-op instead of flooring every tile up to 1 attempt).
+    let base_swaps_per_tile = max_swaps / tile_count;
+    let extra_tiles = max_swaps % tile_count;
+    let swaps_for_tile = |tile_idx: usize| -> usize {
+        base_swaps_per_tile + if tile_idx < extra_tiles { 1 } else { 0 }
+    };
+    let buffer = TileBuffer::new(pixels);
+
+    let mut total_swaps = 0;
+    for phase in 0..4 {
+        let (phase_x, phase_y) = (phase % 2, phase / 2);
+        let tiles: Vec<usize> = (0..tile_count)
+            .filter(|&t| {
+                (t % TILES_PER_SIDE) % 2 == phase_x && (t / TILES_PER_SIDE) % 2 == phase_y
+            })
+            .collect();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let made: usize = tiles
+            .par_iter()
+            .map(|&tile_idx| {
+                run_tile(
+                    tile_idx,
+                    generation,
+                    &buffer,
+                    sidelen,
+                    pixel_data,
+                    target_pixels,
+                    weights,
+                    palette,
+                    proximity_importance,
+                    frame_count,
+                    params,
+                    swaps_for_tile(tile_idx),
+                )
+            })
+            .sum();
+
+        #[cfg(target_arch = "wasm32")]
+        let made: usize = tiles
+            .iter()
+            .map(|&tile_idx| {
+                run_tile(
+                    tile_idx,
+                    generation,
+                    &buffer,
+                    sidelen,
+                    pixel_data,
+                    target_pixels,
+                    weights,
+                    palette,
+                    proximity_importance,
+                    frame_count,
+                    params,
+                    swaps_for_tile(tile_idx),
+                )
+            })
+            .sum();
+
+        total_swaps += made;
+    }
+
+    total_swaps
+}
+
+/// Attempts up to `swaps_per_tile` swaps whose first pixel lives inside tile
+/// `tile_idx`, using an RNG seeded deterministically from `(tile_idx,
+/// generation)`. Because the four-phase schedule in `run_tiled_pass` plus
+/// the half-tile halo keep concurrently-running tiles' write regions
+/// disjoint (see `HALO_SIZE`), there is no write ordering between threads
+/// for this call to race on, so results stay reproducible regardless of
+/// which worker thread happens to draw which tile. The swap partner is
+/// restricted to the tile plus a half-tile halo, matching that schedule.
+#[allow(clippy::too_many_arguments)]
+fn run_tile(
+    tile_idx: usize,
+    generation: u32,
+    buffer: &TileBuffer,
+    sidelen: usize,
+    pixel_data: &[PixelData],
+    target_pixels: &[[f32; 3]],
+    weights: &[i64],
+    palette: &[[f32; 3]],
+    proximity_importance: i64,
+    frame_count: u32,
+    params: &DrawingParams,
+    swaps_per_tile: usize,
+) -> usize {
+    let tile_x = tile_idx % TILES_PER_SIDE;
+    let tile_y = tile_idx / TILES_PER_SIDE;
+
+    let seed = (tile_idx as u64)
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (generation as u64).wrapping_mul(0xBF58_476D_1CE4_E5B9)
+        ^ 0xD6E8_FEB8_6659_FD93;
+    let mut rng = frand::Rand::with_seed(seed);
+
+    let tile_x0 = tile_x * TILE_SIZE;
+    let tile_y0 = tile_y * TILE_SIZE;
+    let halo_x0 = tile_x0.saturating_sub(HALO_SIZE);
+    let halo_y0 = tile_y0.saturating_sub(HALO_SIZE);
+    let halo_x1 = (tile_x0 + TILE_SIZE + HALO_SIZE).min(sidelen);
+    let halo_y1 = (tile_y0 + TILE_SIZE + HALO_SIZE).min(sidelen);
+
+    let mut swaps_made = 0;
+    for _ in 0..swaps_per_tile {
+        let ax = tile_x0 + rng.gen_range(0..TILE_SIZE as u64) as usize;
+        let ay = tile_y0 + rng.gen_range(0..TILE_SIZE as u64) as usize;
+        let apos = ay * sidelen + ax;
+
+        let max_dist_a = params.max_dist(frame_count.saturating_sub(pixel_data[apos].last_edited));
+
+        // Clamped one cell shy of the halo edge on each side: `bpos` feeds
+        // `stroke_reward_neighbors`, which reads one cell further still, and
+        // the halo edge is exactly where the next same-phase tile's own
+        // write zone begins. Without this extra cell, that read lands on
+        // an index the other tile's thread is concurrently writing to.
+        let bx = (ax as i64
+            + rng.gen_range(-(max_dist_a as i64)..(max_dist_a as i64 + 1)))
+            .clamp(halo_x0 as i64 + 1, halo_x1 as i64 - 2) as usize;
+        let by = (ay as i64
+            + rng.gen_range(-(max_dist_a as i64)..(max_dist_a as i64 + 1)))
+            .clamp(halo_y0 as i64 + 1, halo_y1 as i64 - 2) as usize;
+        let bpos = by * sidelen + bx;
+
+        let max_dist_b = params.max_dist(frame_count.saturating_sub(pixel_data[bpos].last_edited));
+        if (bx as i64 - ax as i64).abs() > max_dist_b as i64
+            || (by as i64 - ay as i64).abs() > max_dist_b as i64
+        {
+            continue;
+        }
+
+        let pixel_a = buffer.get(apos);
+        let pixel_b = buffer.get(bpos);
+
+        let t_a = target_pixels[apos];
+        let t_b = target_pixels[bpos];
+
+        let current_a = pixel_a.h
+            + stroke_reward_tiled(apos, apos, pixel_data, buffer, frame_count, params);
+        let current_b = pixel_b.h
+            + stroke_reward_tiled(bpos, bpos, pixel_data, buffer, frame_count, params);
+
+        let a_on_b_base =
+            pixel_a.calc_drawing_heuristic((bx as u16, by as u16), t_b, weights[bpos], palette, proximity_importance);
+        let b_on_a_base =
+            pixel_b.calc_drawing_heuristic((ax as u16, ay as u16), t_a, weights[apos], palette, proximity_importance);
+
+        let a_on_b_h =
+            a_on_b_base + stroke_reward_tiled(bpos, apos, pixel_data, buffer, frame_count, params);
+        let b_on_a_h =
+            b_on_a_base + stroke_reward_tiled(apos, bpos, pixel_data, buffer, frame_count, params);
+
+        let improvement_a = current_a - b_on_a_h;
+        let improvement_b = current_b - a_on_b_h;
+        if improvement_a + improvement_b > 0 {
+            let mut new_a = pixel_b;
+            new_a.update_heuristic(b_on_a_base);
+            let mut new_b = pixel_a;
+            new_b.update_heuristic(a_on_b_base);
+            buffer.set(apos, new_a);
+            buffer.set(bpos, new_b);
+            swaps_made += 1;
+        }
+    }
+
+    swaps_made
+}
+
+fn stroke_reward_tiled(
     newpos: usize,
     oldpos: usize,
     pixel_data: &[PixelData],
-    pixels: &[DrawingPixel],
+    buffer: &TileBuffer,
     frame_count: u32,
     params: &DrawingParams,
+) -> i64 {
+    stroke_reward_neighbors(newpos, oldpos, pixel_data, frame_count, params, |idx| {
+        buffer.get(idx)
+    })
+}
+
+/// Looks at the 4-connected neighbors of `newpos` and rewards keeping a
+/// pixel next to others from the same stroke, using `oldpos`'s stroke id
+/// (the id travels with the source pixel across a swap, not the canvas
+/// cell).
+fn stroke_reward_neighbors(
+    newpos: usize,
+    oldpos: usize,
+    pixel_data: &[PixelData],
+    frame_count: u32,
+    params: &DrawingParams,
+    get_pixel: impl Fn(usize) -> DrawingPixel,
 ) -> i64 {
     let x = (newpos % DRAWING_CANVAS_SIZE) as u16;
     let y = (newpos / DRAWING_CANVAS_SIZE) as u16;
     // look at 8-connected neighbors
     // if any has the same stroke_id, return true
+    let old_pixel = get_pixel(oldpos);
     let data = pixel_data
-        [pixels[oldpos].src_x as usize + pixels[oldpos].src_y as usize * DRAWING_CANVAS_SIZE];
+        [old_pixel.src_x as usize + old_pixel.src_y as usize * DRAWING_CANVAS_SIZE];
     let stroke_id = data.stroke_id;
     let _age = frame_count - data.last_edited;
 
@@ -264,8 +660,8 @@ pub(crate) fn stroke_reward_with_params(
             continue;
         }
         let npos = ny as usize * DRAWING_CANVAS_SIZE + nx as usize;
-        if pixel_data
-            [pixels[npos].src_x as usize + pixels[npos].src_y as usize * DRAWING_CANVAS_SIZE]
+        let n_pixel = get_pixel(npos);
+        if pixel_data[n_pixel.src_x as usize + n_pixel.src_y as usize * DRAWING_CANVAS_SIZE]
             .stroke_id
             == stroke_id
         {
@@ -275,6 +671,39 @@ pub(crate) fn stroke_reward_with_params(
     0
 }
 
+/// Rebuilds the output image from an assignment list (`assignments[i]` is
+/// the index into `source_pixels` that canvas position `i` is currently
+/// showing), the shape `DrawingPixel::src_x`/`src_y` track during the swap
+/// search.
+#[cfg(not(target_arch = "wasm32"))]
+fn render_assignment_image(
+    source_pixels: &[(u8, u8, u8)],
+    assignments: &[usize],
+    sidelen: u32,
+) -> image::RgbaImage {
+    let mut image = image::RgbaImage::new(sidelen, sidelen);
+    for (i, &src_idx) in assignments.iter().enumerate() {
+        let (r, g, b) = source_pixels[src_idx];
+        image.put_pixel(i as u32 % sidelen, i as u32 / sidelen, image::Rgba([r, g, b, 255]));
+    }
+    image
+}
+
+/// Per-generation grain template offset, seeded from the same `(seed,
+/// generation)` pair each time so a given generation's preview is stable
+/// across re-renders, while successive generations don't all sample the
+/// template at the same spot (see `GrainFilter::apply`).
+#[cfg(not(target_arch = "wasm32"))]
+fn grain_offset_for_generation(seed: u64, generation: u32) -> (u32, u32) {
+    let mut rng = frand::Rand::with_seed(
+        seed ^ (generation as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+    );
+    (
+        rng.gen_range(0..calculate::grain::TEMPLATE_SIZE as u64) as u32,
+        rng.gen_range(0..calculate::grain::TEMPLATE_SIZE as u64) as u32,
+    )
+}
+
 #[allow(clippy::too_many_arguments)]
 #[cfg(not(target_arch = "wasm32"))]
 pub fn drawing_process_genetic(
@@ -291,12 +720,25 @@ pub fn drawing_process_genetic(
     let source_img =
         image::ImageBuffer::from_raw(source.width, source.height, source.source_img.clone())
             .unwrap();
-    let (source_pixels, target_pixels, weights) =
-        calculate::util::get_images(source_img, &settings)?;
+    let read_colors: Vec<SeedColor> = colors.read().unwrap().clone();
+    let read_colors = resolve_colors(&source_img, &settings, &read_colors);
+    if settings.palette_size > 0 {
+        // Write the derived palette back so every later generation's
+        // `colors.read()` (used to rebuild `palette` in the loop below) sees
+        // it too, instead of only the pixels built here at construction.
+        *colors.write().unwrap() = read_colors.clone();
+    }
+    let (source_pixels, target_pixels_raw, weights) =
+        calculate::util::get_images(source_img, &settings, &read_colors)?;
+    let target_pixels: Vec<[f32; 3]> = target_pixels_raw
+        .iter()
+        .map(|&c| calculate::color::to_space(c, settings.color_space))
+        .collect();
 
     let mut pixels = {
-        let read_colors: Vec<SeedColor> = colors.read().unwrap().clone();
-        //let read_pixel_data: Vec<PixelData> = pixel_data.read().unwrap().clone();
+        let palette = palette_in_color_space(&read_colors, settings.color_space);
+        let initial_placement =
+            turbulence_initial_placement(settings.sidelen as usize, settings.stroke_seed);
 
         source_pixels
             .iter()
@@ -304,14 +746,14 @@ pub fn drawing_process_genetic(
             .map(|(i, _)| {
                 let x = (i as u32 % settings.sidelen) as u16;
                 let y = (i as u32 / settings.sidelen) as u16;
-                let mut p = DrawingPixel::new(x, y, 0);
+                let (src_x, src_y) = initial_placement.as_ref().map_or((x, y), |p| p[i]);
+                let mut p = DrawingPixel::new(src_x, src_y, 0);
                 let h = p.calc_drawing_heuristic(
                     (x, y),
                     target_pixels[i],
                     weights[i],
-                    &read_colors,
+                    &palette,
                     settings.proximity_importance,
-                    // &read_pixel_data,
                 );
                 p.update_heuristic(h);
                 p
@@ -319,104 +761,59 @@ pub fn drawing_process_genetic(
             .collect::<Vec<_>>()
     };
 
-    let mut rng = frand::Rand::with_seed(12345);
     let swaps_per_generation = SWAPS_PER_GENERATION_PER_PIXEL * pixels.len();
+    let mut generation: u32 = 0;
+    let grain_filter = settings.grain.as_ref().map(calculate::grain::GrainFilter::new);
 
     loop {
-        let colors: Vec<SeedColor> = {
+        // Re-derived once per generation (not once per tile/swap) so the hot
+        // loop below never has to touch `SeedColor`/color-space conversion.
+        let palette: Vec<[f32; 3]> = {
             let r = colors.read().unwrap();
-            r.clone()
+            palette_in_color_space(&r, settings.color_space)
         };
-        let pixel_data = {
+        let pixel_data_snapshot = {
             let r = pixel_data.read().unwrap();
             r.clone()
         };
-        let mut swaps_made = 0;
-
-        for _ in 0..swaps_per_generation {
-            let apos = rng.gen_range(0..pixels.len() as u64) as usize;
-            let ax = apos as u16 % settings.sidelen as u16;
-            let ay = apos as u16 / settings.sidelen as u16;
-
-            //let stroke_id = pixel_data[apos].stroke_id as usize;
-            let max_dist_a = params.max_dist(frame_count.saturating_sub(pixel_data[apos].last_edited));
-
-            let bx = (ax as i16 + rng.gen_range(-(max_dist_a as i16)..(max_dist_a as i16 + 1)))
-                .clamp(0, settings.sidelen as i16 - 1) as u16;
-            let by = (ay as i16 + rng.gen_range(-(max_dist_a as i16)..(max_dist_a as i16 + 1)))
-                .clamp(0, settings.sidelen as i16 - 1) as u16;
-            let bpos = by as usize * settings.sidelen as usize + bx as usize;
-
-            let max_dist_b = params.max_dist(frame_count.saturating_sub(pixel_data[bpos].last_edited));
-            if (bx as i32 - ax as i32).abs() > max_dist_b as i32
-                || (by as i32 - ay as i32).abs() > max_dist_b as i32
-            {
-                continue;
-            }
-
-            let t_a = target_pixels[apos];
-            let t_b = target_pixels[bpos];
-
-            let current_a = pixels[apos].h
-                + stroke_reward_with_params(apos, apos, &pixel_data, &pixels, frame_count, &params);
-            let current_b = pixels[bpos].h
-                + stroke_reward_with_params(bpos, bpos, &pixel_data, &pixels, frame_count, &params);
-
-            let a_on_b_base = pixels[apos].calc_drawing_heuristic(
-                (bx, by),
-                t_b,
-                weights[bpos],
-                &colors,
-                settings.proximity_importance,
-            );
 
-            let b_on_a_base = pixels[bpos].calc_drawing_heuristic(
-                (ax, ay),
-                t_a,
-                weights[apos],
-                &colors,
-                settings.proximity_importance,
-            );
-            let a_on_b_h = a_on_b_base
-                + stroke_reward_with_params(bpos, apos, &pixel_data, &pixels, frame_count, &params);
-            let b_on_a_h = b_on_a_base
-                + stroke_reward_with_params(apos, bpos, &pixel_data, &pixels, frame_count, &params);
-
-            let improvement_a = current_a - b_on_a_h;
-            let improvement_b = current_b - a_on_b_h;
-            if improvement_a + improvement_b > 0 {
-                // swap
-                pixels.swap(apos, bpos);
-                pixels[apos].update_heuristic(b_on_a_base);
-                pixels[bpos].update_heuristic(a_on_b_base);
-                swaps_made += 1;
-            }
-        }
+        let swaps_made = run_tiled_pass(
+            &mut pixels,
+            &pixel_data_snapshot,
+            &target_pixels,
+            &weights,
+            &palette,
+            settings.sidelen as usize,
+            settings.proximity_importance,
+            frame_count,
+            &params,
+            generation,
+            swaps_per_generation,
+        );
+        generation = generation.wrapping_add(1);
 
-        //println!("swaps made: {}", swaps_made);
-
-        // let img = make_new_img(&source_pixels, &assignments, target.width());
-        // if swaps_made < 10 || cancelled.load(std::sync::atomic::Ordering::Relaxed) {
-        //     let dir_name = save_result(target, base_name, source, assignments, img)?;
-        //     tx.send(ProgressMsg::Done(PathBuf::from(format!(
-        //         "./presets/{}",
-        //         dir_name
-        //     ))))?;
-        //     return Ok(());
-        // }
-        // tx.send(ProgressMsg::UpdatePreview(img))?;
         if swaps_made > 0 {
             let assignments = pixels
                 .iter()
                 .map(|p| p.src_y as usize * settings.sidelen as usize + p.src_x as usize)
                 .collect::<Vec<_>>();
+            if let Some(filter) = &grain_filter {
+                // Applied after assignment readback so it never feeds back
+                // into the swap heuristic above.
+                let mut image =
+                    render_assignment_image(&source_pixels, &assignments, settings.sidelen);
+                let offset = grain_offset_for_generation(
+                    settings.grain.as_ref().unwrap().seed,
+                    generation,
+                );
+                filter.apply(&mut image, offset);
+                tx.send(ProgressMsg::UpdatePreview(image))?;
+            }
             tx.send(ProgressMsg::UpdateAssignments(assignments))?;
         }
         if my_id != current_id.load(std::sync::atomic::Ordering::Relaxed) {
             tx.send(ProgressMsg::Cancelled).unwrap();
             return Ok(());
         }
-
-        //max_dist = (max_dist as f32 * 0.99).max(4.0) as u32;
     }
 }
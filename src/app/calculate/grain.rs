@@ -0,0 +1,101 @@
+use image::{Rgba, RgbaImage};
+
+/// Parameters for the optional film-grain pass applied to the rendered
+/// assignment image.
+#[derive(Clone, Copy, Debug)]
+pub struct GrainParams {
+    pub seed: u64,
+    /// Overall grain strength; multiplies the luma-scaled noise before it's
+    /// added to each pixel.
+    pub intensity: f32,
+    /// 0 = independent per-cell white noise, closer to 1 = more spatially
+    /// correlated grain (first-order AR filter in both axes).
+    pub correlation: f32,
+}
+
+pub(crate) const TEMPLATE_SIZE: usize = 64;
+
+/// Film grain synthesis: a small, seeded noise template is tiled across the
+/// image (with a caller-supplied offset so repeated frames don't show an
+/// obviously static pattern) and scaled per pixel by a luma-dependent LUT,
+/// so shadows and highlights can be grained differently.
+pub struct GrainFilter {
+    template: [[f32; TEMPLATE_SIZE]; TEMPLATE_SIZE],
+    scaling_lut: [f32; 256],
+    intensity: f32,
+}
+
+impl GrainFilter {
+    pub fn new(params: &GrainParams) -> Self {
+        Self {
+            template: build_template(params.seed, params.correlation),
+            scaling_lut: build_scaling_lut(),
+            intensity: params.intensity,
+        }
+    }
+
+    /// Applies the grain in place. Call this after pixel-assignment readback
+    /// so it never feeds back into the swap heuristic.
+    pub fn apply(&self, image: &mut RgbaImage, offset: (u32, u32)) {
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let Rgba([r, g, b, a]) = *pixel;
+            let tx = ((x.wrapping_add(offset.0)) as usize) % TEMPLATE_SIZE;
+            let ty = ((y.wrapping_add(offset.1)) as usize) % TEMPLATE_SIZE;
+            let grain = self.template[ty][tx];
+
+            let luma =
+                (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32).clamp(0.0, 255.0) as usize;
+            let delta = grain * self.intensity * self.scaling_lut[luma];
+
+            *pixel = Rgba([
+                (r as f32 + delta).clamp(0.0, 255.0) as u8,
+                (g as f32 + delta).clamp(0.0, 255.0) as u8,
+                (b as f32 + delta).clamp(0.0, 255.0) as u8,
+                a,
+            ]);
+        }
+    }
+}
+
+/// Fills a 64x64 template with zero-mean Gaussian noise (Box-Muller), then
+/// optionally runs a first-order autoregressive filter along each axis so
+/// the grain reads as spatially correlated rather than per-pixel white
+/// noise.
+fn build_template(seed: u64, correlation: f32) -> [[f32; TEMPLATE_SIZE]; TEMPLATE_SIZE] {
+    let mut rng = frand::Rand::with_seed(seed);
+    let mut template = [[0f32; TEMPLATE_SIZE]; TEMPLATE_SIZE];
+
+    for row in template.iter_mut() {
+        for cell in row.iter_mut() {
+            let u1 = (rng.gen_range(1u32..u32::MAX) as f64 / u32::MAX as f64) as f32;
+            let u2 = (rng.gen_range(0u32..u32::MAX) as f64 / u32::MAX as f64) as f32;
+            *cell = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos();
+        }
+    }
+
+    if correlation > 0.0 {
+        for row in template.iter_mut() {
+            for x in 1..TEMPLATE_SIZE {
+                row[x] = correlation * row[x - 1] + (1.0 - correlation) * row[x];
+            }
+        }
+        for x in 0..TEMPLATE_SIZE {
+            for y in 1..TEMPLATE_SIZE {
+                template[y][x] = correlation * template[y - 1][x] + (1.0 - correlation) * template[y][x];
+            }
+        }
+    }
+
+    template
+}
+
+/// Luma -> grain-strength multiplier. Grain reads most visibly in midtones,
+/// so it's rolled off towards pure black/white rather than applied flat.
+fn build_scaling_lut() -> [f32; 256] {
+    let mut lut = [0f32; 256];
+    for (luma, slot) in lut.iter_mut().enumerate() {
+        let t = luma as f32 / 255.0;
+        *slot = 4.0 * t * (1.0 - t);
+    }
+    lut
+}
@@ -0,0 +1,84 @@
+pub mod color;
+pub mod drawing_process;
+pub mod grain;
+pub mod noise;
+pub mod palette;
+pub mod util;
+
+use std::path::PathBuf;
+
+#[cfg(not(target_arch = "wasm32"))]
+use image::RgbaImage;
+
+pub use color::ColorSpace;
+pub use grain::GrainParams;
+pub use noise::StrokeSeed;
+pub use util::DitherMode;
+
+pub(crate) const SWAPS_PER_GENERATION_PER_PIXEL: usize = 4;
+
+/// How aggressively the genetic swap search should chase exact target colors
+/// versus keeping pixels close to their stroke neighbors. Shared by both the
+/// `DrawingState::step` and the background `drawing_process_genetic`.
+#[derive(Clone)]
+pub struct GenerationSettings {
+    pub sidelen: u32,
+    pub proximity_importance: i64,
+    pub target_width: u32,
+    pub target_height: u32,
+    pub target_img: Vec<u8>,
+    pub dither: DitherMode,
+    /// Number of colors `palette::derive_palette` should derive when the
+    /// caller asks for an automatic palette instead of a curated `SeedColor`
+    /// set.
+    pub palette_size: u32,
+    /// Color space the swap heuristic measures distance in.
+    pub color_space: ColorSpace,
+    /// How `PixelData::init_canvas` seeds the initial `stroke_id` field.
+    pub stroke_seed: StrokeSeed,
+    /// Optional film-grain pass applied to the rendered assignment image
+    /// after readback, for a photographic/analog texture. See
+    /// `grain::GrainFilter`.
+    pub grain: Option<GrainParams>,
+    /// Scales each pixel's swap weight by `1 + edge_boost * normalized_gradient`
+    /// using a Sobel edge map of the target, so high-detail edges get extra
+    /// priority over flat regions. `0.0` disables the effect.
+    pub edge_boost: f32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub enum ProgressMsg {
+    UpdatePreview(RgbaImage),
+    UpdateAssignments(Vec<usize>),
+    Done(PathBuf),
+    Cancelled,
+}
+
+/// Base cost for assigning a source pixel to a target position: a weighted
+/// color-distance term plus a proximity term that discourages pixels from
+/// traveling far from their original location.
+///
+/// `src_col`/`target_col` are triplets in whichever color space
+/// `GenerationSettings::color_space` selected (raw RGB channels, or CIELAB
+/// `[L, a, b]`) — the squared-Euclidean form is the same either way, only
+/// the units differ, which is the square of ΔE76 when the space is Lab.
+#[inline(always)]
+pub fn heuristic(
+    src_pos: (u16, u16),
+    target_pos: (u16, u16),
+    src_col: [f32; 3],
+    target_col: [f32; 3],
+    weight: i64,
+    proximity_importance: i64,
+) -> i64 {
+    let dr = src_col[0] - target_col[0];
+    let dg = src_col[1] - target_col[1];
+    let db = src_col[2] - target_col[2];
+    let color_term = (dr * dr + dg * dg + db * db) as i64 * weight;
+
+    let dx = src_pos.0 as i64 - target_pos.0 as i64;
+    let dy = src_pos.1 as i64 - target_pos.1 as i64;
+    let proximity_term = (dx * dx + dy * dy) * proximity_importance;
+
+    color_term + proximity_term
+}
@@ -0,0 +1,136 @@
+/// How the initial `stroke_id` field (and, by extension, which pixels the
+/// stroke-reward term treats as "the same brush stroke") is seeded.
+#[derive(Clone, Copy, Debug)]
+pub enum StrokeSeed {
+    /// The original behavior: every pixel starts in stroke 0.
+    Grid,
+    /// Fractal Perlin turbulence, quantized into bands, so strokes start out
+    /// as organic, flowing regions instead of one uniform blob. The same
+    /// field also nudges the initial source-pixel placement along its flow
+    /// (see `drawing_process::turbulence_initial_placement`) instead of
+    /// leaving it on the identity grid.
+    Turbulence {
+        octaves: u32,
+        frequency: f32,
+        persistence: f32,
+    },
+}
+
+impl Default for StrokeSeed {
+    fn default() -> Self {
+        StrokeSeed::Grid
+    }
+}
+
+const PERM_SIZE: usize = 256;
+
+/// Classic Perlin noise: a permutation table hashes lattice coordinates to
+/// one of 8 unit gradient directions, and the four surrounding corners are
+/// blended with a quintic fade curve.
+pub struct Perlin {
+    perm: [u8; PERM_SIZE * 2],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut table: [u8; PERM_SIZE] = [0; PERM_SIZE];
+        for (i, slot) in table.iter_mut().enumerate() {
+            *slot = i as u8;
+        }
+
+        let mut rng = frand::Rand::with_seed(seed);
+        for i in (1..PERM_SIZE).rev() {
+            let j = rng.gen_range(0..(i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+
+        let mut perm = [0u8; PERM_SIZE * 2];
+        for (i, slot) in perm.iter_mut().enumerate() {
+            *slot = table[i % PERM_SIZE];
+        }
+
+        Self { perm }
+    }
+
+    #[inline(always)]
+    fn hash(&self, x: i32, y: i32) -> u8 {
+        let xi = (x & (PERM_SIZE as i32 - 1)) as usize;
+        let yi = (y & (PERM_SIZE as i32 - 1)) as usize;
+        self.perm[self.perm[xi] as usize + yi]
+    }
+
+    /// Samples noise in roughly [-1, 1] at continuous coordinates `(x, y)`.
+    pub fn noise(&self, x: f32, y: f32) -> f32 {
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let xi = x0 as i32;
+        let yi = y0 as i32;
+        let xf = x - x0;
+        let yf = y - y0;
+
+        let u = fade(xf);
+        let v = fade(yf);
+
+        let aa = self.hash(xi, yi);
+        let ab = self.hash(xi, yi + 1);
+        let ba = self.hash(xi + 1, yi);
+        let bb = self.hash(xi + 1, yi + 1);
+
+        let x1 = lerp(grad(aa, xf, yf), grad(ba, xf - 1.0, yf), u);
+        let x2 = lerp(grad(ab, xf, yf - 1.0), grad(bb, xf - 1.0, yf - 1.0), u);
+        lerp(x1, x2, v)
+    }
+}
+
+#[inline(always)]
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+#[inline(always)]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// Projects a permutation-table hash onto one of 8 unit gradient directions
+/// and dots it with the offset from the lattice point.
+#[inline(always)]
+fn grad(hash: u8, x: f32, y: f32) -> f32 {
+    use std::f32::consts::FRAC_1_SQRT_2;
+    match hash & 7 {
+        0 => (x + y) * FRAC_1_SQRT_2,
+        1 => (-x + y) * FRAC_1_SQRT_2,
+        2 => (x - y) * FRAC_1_SQRT_2,
+        3 => (-x - y) * FRAC_1_SQRT_2,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+/// Fractal sum of Perlin noise ("turbulence"): `octaves` layers, each
+/// doubling frequency and scaling amplitude by `persistence`, normalized so
+/// the result stays in roughly [-1, 1] regardless of octave count.
+pub fn turbulence(
+    perlin: &Perlin,
+    x: f32,
+    y: f32,
+    octaves: u32,
+    frequency: f32,
+    persistence: f32,
+) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut freq = frequency;
+    let mut max_amplitude = 0.0;
+
+    for _ in 0..octaves.max(1) {
+        total += perlin.noise(x * freq, y * freq) * amplitude;
+        max_amplitude += amplitude;
+        amplitude *= persistence;
+        freq *= 2.0;
+    }
+
+    total / max_amplitude.max(f32::EPSILON)
+}
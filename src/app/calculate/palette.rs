@@ -0,0 +1,157 @@
+use crate::app::SeedColor;
+
+/// An axis-aligned box over sampled pixel colors, used by median-cut to
+/// recursively partition the color space.
+struct ColorBox {
+    pixels: Vec<[u8; 3]>,
+}
+
+impl ColorBox {
+    fn channel_range(&self, channel: usize) -> (u8, u8) {
+        let mut lo = u8::MAX;
+        let mut hi = u8::MIN;
+        for p in &self.pixels {
+            lo = lo.min(p[channel]);
+            hi = hi.max(p[channel]);
+        }
+        (lo, hi)
+    }
+
+    /// The channel with the widest spread of values in this box.
+    fn longest_axis(&self) -> usize {
+        (0..3)
+            .max_by_key(|&c| {
+                let (lo, hi) = self.channel_range(c);
+                hi as i32 - lo as i32
+            })
+            .unwrap()
+    }
+
+    /// `range * population` — boxes that are both wide and well populated are
+    /// the ones worth splitting first.
+    fn score(&self) -> u64 {
+        let axis = self.longest_axis();
+        let (lo, hi) = self.channel_range(axis);
+        (hi as u64 - lo as u64) * self.pixels.len() as u64
+    }
+
+    fn mean_color(&self) -> [f32; 3] {
+        let mut sum = [0f64; 3];
+        for p in &self.pixels {
+            for c in 0..3 {
+                sum[c] += p[c] as f64;
+            }
+        }
+        let n = self.pixels.len().max(1) as f64;
+        [
+            (sum[0] / n) as f32,
+            (sum[1] / n) as f32,
+            (sum[2] / n) as f32,
+        ]
+    }
+
+    fn split(mut self) -> (ColorBox, ColorBox) {
+        let axis = self.longest_axis();
+        self.pixels
+            .sort_unstable_by_key(|p| p[axis]);
+        let mid = self.pixels.len() / 2;
+        let upper = self.pixels.split_off(mid);
+        (ColorBox { pixels: self.pixels }, ColorBox { pixels: upper })
+    }
+}
+
+/// Median-cut: repeatedly split the box with the largest `range * population`
+/// along its longest channel axis until there are `n` boxes.
+fn median_cut(pixels: &[(u8, u8, u8)], n: usize) -> Vec<ColorBox> {
+    let mut boxes = vec![ColorBox {
+        pixels: pixels.iter().map(|&(r, g, b)| [r, g, b]).collect(),
+    }];
+
+    while boxes.len() < n {
+        let Some((idx, _)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.pixels.len() > 1)
+            .max_by_key(|(_, b)| b.score())
+        else {
+            break;
+        };
+        let candidate = boxes.swap_remove(idx);
+        let (a, b) = candidate.split();
+        boxes.push(a);
+        boxes.push(b);
+    }
+
+    boxes
+}
+
+/// Derives an `n`-color palette from sampled pixels via median-cut followed
+/// by Lloyd k-means refinement, so the result settles on the colors that
+/// actually minimize assignment error rather than the median-cut boxes'
+/// arbitrary splits.
+pub fn derive_palette(pixels: &[(u8, u8, u8)], n: usize) -> Vec<SeedColor> {
+    if pixels.is_empty() || n == 0 {
+        return Vec::new();
+    }
+
+    let boxes = median_cut(pixels, n);
+    let reseed_candidates: Vec<[f32; 3]> = {
+        let mut by_population = boxes.iter().collect::<Vec<_>>();
+        by_population.sort_by_key(|b| std::cmp::Reverse(b.pixels.len()));
+        by_population.into_iter().map(|b| b.mean_color()).collect()
+    };
+    let mut centers: Vec<[f32; 3]> = boxes.iter().map(ColorBox::mean_color).collect();
+
+    const KMEANS_ITERATIONS: usize = 8;
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![[0f64; 3]; centers.len()];
+        let mut counts = vec![0u64; centers.len()];
+
+        for &(r, g, b) in pixels {
+            let p = [r as f32, g as f32, b as f32];
+            let nearest = nearest_center(p, &centers);
+            sums[nearest][0] += p[0] as f64;
+            sums[nearest][1] += p[1] as f64;
+            sums[nearest][2] += p[2] as f64;
+            counts[nearest] += 1;
+        }
+
+        let mut next_reseed = 0;
+        for (i, center) in centers.iter_mut().enumerate() {
+            if counts[i] == 0 {
+                // Re-seed empty clusters from the most populous median-cut box
+                // that hasn't already re-seeded another cluster this pass.
+                *center = reseed_candidates[next_reseed % reseed_candidates.len()];
+                next_reseed += 1;
+                continue;
+            }
+            let count = counts[i] as f64;
+            *center = [
+                (sums[i][0] / count) as f32,
+                (sums[i][1] / count) as f32,
+                (sums[i][2] / count) as f32,
+            ];
+        }
+    }
+
+    centers
+        .into_iter()
+        .map(|c| SeedColor {
+            rgba: [c[0] / 256.0, c[1] / 256.0, c[2] / 256.0, 1.0],
+        })
+        .collect()
+}
+
+fn nearest_center(p: [f32; 3], centers: &[[f32; 3]]) -> usize {
+    centers
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, c)| {
+            let dr = p[0] - c[0];
+            let dg = p[1] - c[1];
+            let db = p[2] - c[2];
+            (dr * dr + dg * dg + db * db) as i64
+        })
+        .map(|(i, _)| i)
+        .unwrap()
+}
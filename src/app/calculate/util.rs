@@ -0,0 +1,238 @@
+use std::error::Error;
+
+use image::RgbaImage;
+
+use crate::app::SeedColor;
+
+use super::GenerationSettings;
+use super::color::{self, ColorSpace};
+
+/// Error-diffusion strategy applied to the target image before it is used
+/// as the per-pixel swap target, so flat regions the palette can't hit
+/// exactly still read as smooth gradients instead of hard bands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DitherMode {
+    #[default]
+    None,
+    FloydSteinberg,
+}
+
+/// Resamples the source and target images onto the `sidelen`x`sidelen`
+/// working canvas and derives the per-pixel target color and swap weight
+/// used by `DrawingState`.
+pub fn get_images(
+    source_img: RgbaImage,
+    settings: &GenerationSettings,
+    colors: &[SeedColor],
+) -> Result<(Vec<(u8, u8, u8)>, Vec<(u8, u8, u8)>, Vec<i64>), Box<dyn Error>> {
+    let sidelen = settings.sidelen;
+
+    let source_small = image::imageops::resize(
+        &source_img,
+        sidelen,
+        sidelen,
+        image::imageops::FilterType::Triangle,
+    );
+    let source_pixels: Vec<(u8, u8, u8)> =
+        source_small.pixels().map(|p| (p[0], p[1], p[2])).collect();
+
+    let target_img = image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(
+        settings.target_width,
+        settings.target_height,
+        settings.target_img.clone(),
+    )
+    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid target image"))?;
+    let target_small = image::imageops::resize(
+        &target_img,
+        sidelen,
+        sidelen,
+        image::imageops::FilterType::Triangle,
+    );
+
+    let target_pixels = match settings.dither {
+        DitherMode::None => target_small.pixels().map(|p| (p[0], p[1], p[2])).collect(),
+        DitherMode::FloydSteinberg => {
+            dither_floyd_steinberg(&target_small, colors, settings.color_space)
+        }
+    };
+
+    let base_weight = 1024i64;
+    let weights = if settings.edge_boost > 0.0 {
+        let edge_map = sobel_edge_map(&target_small);
+        edge_map
+            .iter()
+            .map(|&e| (base_weight as f32 * (1.0 + settings.edge_boost * e)) as i64)
+            .collect()
+    } else {
+        vec![base_weight; (sidelen * sidelen) as usize]
+    };
+
+    Ok((source_pixels, target_pixels, weights))
+}
+
+/// 3x3 Sobel gradient magnitude of the target image's luma, normalized to
+/// [0, 1] over the whole image so `edge_boost` scales consistently across
+/// different targets. Steers the swap search to spend its effort preserving
+/// edges/silhouettes rather than treating every pixel's weight uniformly.
+fn sobel_edge_map(target: &RgbaImage) -> Vec<f32> {
+    let width = target.width() as i64;
+    let height = target.height() as i64;
+
+    let luma = |x: i64, y: i64| -> f32 {
+        let x = x.clamp(0, width - 1) as u32;
+        let y = y.clamp(0, height - 1) as u32;
+        let p = target.get_pixel(x, y);
+        0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32
+    };
+
+    let mut magnitudes = vec![0f32; (width * height) as usize];
+    let mut max_magnitude = f32::EPSILON;
+
+    for y in 0..height {
+        for x in 0..width {
+            let gx = -luma(x - 1, y - 1) - 2.0 * luma(x - 1, y) - luma(x - 1, y + 1)
+                + luma(x + 1, y - 1)
+                + 2.0 * luma(x + 1, y)
+                + luma(x + 1, y + 1);
+            let gy = -luma(x - 1, y - 1) - 2.0 * luma(x, y - 1) - luma(x + 1, y - 1)
+                + luma(x - 1, y + 1)
+                + 2.0 * luma(x, y + 1)
+                + luma(x + 1, y + 1);
+
+            let magnitude = (gx * gx + gy * gy).sqrt();
+            magnitudes[(y * width + x) as usize] = magnitude;
+            max_magnitude = max_magnitude.max(magnitude);
+        }
+    }
+
+    for m in &mut magnitudes {
+        *m /= max_magnitude;
+    }
+    magnitudes
+}
+
+/// Serpentine Floyd-Steinberg error diffusion: quantize each target pixel to
+/// the nearest color the current palette can actually place, then push the
+/// quantization error onto not-yet-visited neighbors (7/16 right, 3/16
+/// below-left, 5/16 below, 1/16 below-right), flipping direction every row
+/// so the diffusion doesn't streak in one direction. "Nearest" is measured
+/// in `color_space` (mirroring the swap heuristic), though the diffused
+/// error itself stays in raw sRGB — it's the quantization residual of the
+/// pixels actually being written, not a perceptual quantity.
+fn dither_floyd_steinberg(
+    target: &RgbaImage,
+    colors: &[SeedColor],
+    color_space: ColorSpace,
+) -> Vec<(u8, u8, u8)> {
+    let width = target.width() as usize;
+    let height = target.height() as usize;
+    let palette = unique_palette(colors);
+    let palette_space: Vec<[f32; 3]> = palette
+        .iter()
+        .map(|&rgb| color::to_space(rgb, color_space))
+        .collect();
+
+    let mut buf: Vec<[f32; 3]> = target
+        .pixels()
+        .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+        .collect();
+    let mut out = vec![(0u8, 0u8, 0u8); width * height];
+
+    for y in 0..height {
+        let serpentine = y % 2 == 1;
+        let step: i64 = if serpentine { -1 } else { 1 };
+        let xs: Vec<usize> = if serpentine {
+            (0..width).rev().collect()
+        } else {
+            (0..width).collect()
+        };
+
+        for x in xs {
+            let idx = y * width + x;
+            let original = buf[idx];
+            let chosen = nearest_palette_color(original, &palette, &palette_space, color_space);
+            out[idx] = chosen;
+
+            let error = [
+                original[0] - chosen.0 as f32,
+                original[1] - chosen.1 as f32,
+                original[2] - chosen.2 as f32,
+            ];
+
+            let x = x as i64;
+            let y = y as i64;
+            add_error(&mut buf, width, height, x + step, y, error, 7.0 / 16.0);
+            add_error(&mut buf, width, height, x - step, y + 1, error, 3.0 / 16.0);
+            add_error(&mut buf, width, height, x, y + 1, error, 5.0 / 16.0);
+            add_error(&mut buf, width, height, x + step, y + 1, error, 1.0 / 16.0);
+        }
+    }
+
+    out
+}
+
+fn add_error(
+    buf: &mut [[f32; 3]],
+    width: usize,
+    height: usize,
+    x: i64,
+    y: i64,
+    error: [f32; 3],
+    weight: f32,
+) {
+    if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+        return;
+    }
+    let idx = y as usize * width + x as usize;
+    for c in 0..3 {
+        buf[idx][c] = (buf[idx][c] + error[c] * weight).clamp(0.0, 255.0);
+    }
+}
+
+fn unique_palette(colors: &[SeedColor]) -> Vec<(u8, u8, u8)> {
+    let mut seen = std::collections::HashSet::new();
+    let mut palette = Vec::new();
+    for c in colors {
+        let rgb = (
+            (c.rgba[0] * 256.0) as u8,
+            (c.rgba[1] * 256.0) as u8,
+            (c.rgba[2] * 256.0) as u8,
+        );
+        if seen.insert(rgb) {
+            palette.push(rgb);
+        }
+    }
+    if palette.is_empty() {
+        palette.push((0, 0, 0));
+    }
+    palette
+}
+
+fn nearest_palette_color(
+    target: [f32; 3],
+    palette: &[(u8, u8, u8)],
+    palette_space: &[[f32; 3]],
+    color_space: ColorSpace,
+) -> (u8, u8, u8) {
+    let rounded = (
+        target[0].clamp(0.0, 255.0) as u8,
+        target[1].clamp(0.0, 255.0) as u8,
+        target[2].clamp(0.0, 255.0) as u8,
+    );
+    let target_space = color::to_space(rounded, color_space);
+    palette
+        .iter()
+        .copied()
+        .zip(palette_space)
+        .min_by(|&(_, a), &(_, b)| {
+            let dist = |c: &[f32; 3]| {
+                let dr = target_space[0] - c[0];
+                let dg = target_space[1] - c[1];
+                let db = target_space[2] - c[2];
+                dr * dr + dg * dg + db * db
+            };
+            dist(a).total_cmp(&dist(b))
+        })
+        .map(|(rgb, _)| rgb)
+        .unwrap()
+}
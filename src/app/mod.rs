@@ -0,0 +1,10 @@
+pub mod calculate;
+pub mod preset;
+
+pub use calculate::{GenerationSettings, ProgressMsg, heuristic};
+
+/// A single paintable color, as sampled onto the 128x128 working canvas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SeedColor {
+    pub rgba: [f32; 4],
+}
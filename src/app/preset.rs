@@ -0,0 +1,10 @@
+/// A source/target image pair before it has been reduced onto the drawing canvas.
+#[derive(Clone)]
+pub struct UnprocessedPreset {
+    pub width: u32,
+    pub height: u32,
+    pub source_img: Vec<u8>,
+    pub target_width: u32,
+    pub target_height: u32,
+    pub target_img: Vec<u8>,
+}